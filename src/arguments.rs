@@ -0,0 +1,81 @@
+use std::net::SocketAddr;
+
+use clap::Parser;
+
+/// Command-line options for the `piano-rs` binary, covering the multiplayer
+/// network setup, local keyboard/recording behavior, and the render-to-file
+/// mode that bypasses the terminal/network entirely.
+#[derive(Debug, Parser)]
+#[command(name = "piano-rs", about = "A terminal piano, playable solo, over the network, or from a file")]
+pub struct Options {
+    /// Address this instance listens for incoming note/network events on.
+    #[arg(long, default_value = "127.0.0.1:8080")]
+    pub receiver_address: SocketAddr,
+
+    /// Address this instance sends its own note/network events from.
+    #[arg(long, default_value = "127.0.0.1:8081")]
+    pub sender_address: SocketAddr,
+
+    /// Address of the host to register with for a multiplayer session.
+    #[arg(long, default_value = "127.0.0.1:8080")]
+    pub host_address: SocketAddr,
+
+    /// Key sequence mapped onto the piano's keys, left to right.
+    #[arg(long, default_value = "zsxdcvgbhnjmq2w3er5t6y7ui9o0p")]
+    pub sequence: String,
+
+    /// Playback/record volume, from 0.0 (silent) to 1.0 (full scale).
+    #[arg(long, default_value_t = 1.0)]
+    pub volume: f32,
+
+    /// How long a played note stays highlighted, in milliseconds.
+    #[arg(long, default_value_t = 1500)]
+    pub note_duration: u64,
+
+    /// How long a key stays marked after being released, in milliseconds.
+    #[arg(long, default_value_t = 100)]
+    pub mark_duration: u64,
+
+    /// TOML file to load player/keyboard colors from.
+    #[arg(long)]
+    pub theme: Option<String>,
+
+    /// `.notes` file to record this session's notes to.
+    #[arg(long)]
+    pub record_file: Option<String>,
+
+    /// `.notes` file (or `.xspf` playlist) to play back instead of reading
+    /// from the keyboard.
+    #[arg(long)]
+    pub play_file: Option<String>,
+
+    /// Tempo multiplier applied to `play_file`'s recorded delays, or to any
+    /// playlist track that doesn't specify its own.
+    #[arg(long, default_value_t = 1.0)]
+    pub play_file_tempo: f32,
+
+    /// Repeat the `play_file` playlist instead of stopping after one pass.
+    #[arg(long, default_value_t = false)]
+    pub loop_playlist: bool,
+
+    /// `.notes` file to render to an audio file instead of playing live.
+    /// Bypasses the terminal and network setup entirely.
+    #[arg(long)]
+    pub render_file: Option<String>,
+
+    /// Output audio file path for `render_file` (`.wav`, or `.ogg` with the
+    /// `vorbis` feature enabled). Defaults to `output.wav`.
+    #[arg(long)]
+    pub render_output: Option<String>,
+
+    /// Caps the rendered audio's sample rate, downsampling from 44.1kHz if
+    /// set lower. Leaves the sample rate untouched if omitted.
+    #[arg(long)]
+    pub max_samplerate: Option<u32>,
+}
+
+impl Options {
+    pub fn read() -> Options {
+        Options::parse()
+    }
+}
@@ -0,0 +1,38 @@
+use thiserror::Error;
+
+/// Crate-wide error type. Fatal faults (terminal init or socket bind
+/// failure) propagate out of `main`; recoverable ones (a bad network
+/// packet, an unparseable peer address) are logged and skipped by the
+/// caller instead.
+#[derive(Debug, Error)]
+pub enum PianoError {
+    #[error("terminal or network I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("invalid socket address: {0}")]
+    InvalidAddress(#[from] std::net::AddrParseError),
+
+    #[error("malformed network packet: {0}")]
+    InvalidPacket(String),
+}
+
+pub type Result<T> = std::result::Result<T, PianoError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn io_error_converts_via_from() {
+        let io_error = std::io::Error::new(std::io::ErrorKind::NotFound, "missing");
+        let error: PianoError = io_error.into();
+        assert!(matches!(error, PianoError::Io(_)));
+    }
+
+    #[test]
+    fn addr_parse_error_converts_via_from() {
+        let parse_error = "not an address".parse::<std::net::SocketAddr>().unwrap_err();
+        let error: PianoError = parse_error.into();
+        assert!(matches!(error, PianoError::InvalidAddress(_)));
+    }
+}
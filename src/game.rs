@@ -0,0 +1,360 @@
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crossterm::event::KeyCode;
+use rodio::buffer::SamplesBuffer;
+use rodio::{OutputStream, Source};
+
+use crate::terminal::{Color, KeyEvent, Terminal};
+
+const SAMPLE_RATE: u32 = 44_100;
+
+/// A fully-synthesized note: the note name it was parsed from (used to
+/// round-trip it over the network and into a `.notes` record file), the
+/// color the player who struck it is drawn in, and the PCM samples both
+/// live playback and the offline renderer mix into their output.
+#[derive(Debug, Clone)]
+pub struct Note {
+    pub base_note: String,
+    pub color: Color,
+    pub duration: Duration,
+    samples: Vec<i16>,
+}
+
+/// An unrecognized note name, e.g. a malformed `.notes` line or an
+/// out-of-range octave.
+#[derive(Debug)]
+pub struct NoteError(String);
+
+impl fmt::Display for NoteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unrecognized note '{}'", self.0)
+    }
+}
+
+impl std::error::Error for NoteError {}
+
+impl Note {
+    /// Synthesizes a sine wave at `base_note`'s equal-tempered frequency,
+    /// e.g. `"A4"`, `"C#4"`, `"Db4"`. Fails on anything that doesn't parse
+    /// as `<letter>[#|b]<octave>`.
+    pub fn from(base_note: &str, color: Color, duration: Duration) -> Result<Note, NoteError> {
+        let frequency = note_frequency(base_note).ok_or_else(|| NoteError(base_note.to_string()))?;
+        Ok(Note {
+            base_note: base_note.to_string(),
+            color,
+            duration,
+            samples: sine_wave(frequency, duration),
+        })
+    }
+
+    pub fn samples(&self) -> &[i16] {
+        &self.samples
+    }
+}
+
+fn sine_wave(frequency: f32, duration: Duration) -> Vec<i16> {
+    let sample_count = (duration.as_secs_f32() * SAMPLE_RATE as f32) as usize;
+    (0..sample_count)
+        .map(|i| {
+            let t = i as f32 / SAMPLE_RATE as f32;
+            ((2.0 * std::f32::consts::PI * frequency * t).sin() * i16::MAX as f32) as i16
+        })
+        .collect()
+}
+
+/// Standard equal-tempered frequency for a note name, with A4 = 440Hz.
+fn note_frequency(name: &str) -> Option<f32> {
+    if !name.is_ascii() {
+        return None;
+    }
+    let bytes = name.as_bytes();
+    if bytes.is_empty() {
+        return None;
+    }
+    let has_accidental = bytes.len() > 1 && (bytes[1] == b'#' || bytes[1] == b'b');
+    let letter_len = if has_accidental { 2 } else { 1 };
+
+    let semitone = match &name[..letter_len] {
+        "C" => 0,
+        "C#" | "Db" => 1,
+        "D" => 2,
+        "D#" | "Eb" => 3,
+        "E" => 4,
+        "F" => 5,
+        "F#" | "Gb" => 6,
+        "G" => 7,
+        "G#" | "Ab" => 8,
+        "A" => 9,
+        "A#" | "Bb" => 10,
+        "B" => 11,
+        _ => return None,
+    };
+    let octave: i32 = name[letter_len..].parse().ok()?;
+
+    let semitones_from_a4 = (octave - 4) * 12 + (semitone - 9);
+    Some(440.0 * 2f32.powf(semitones_from_a4 as f32 / 12.0))
+}
+
+/// What `PianoKeyboard::process_key` produced for a single keystroke.
+pub enum GameEvent {
+    Note(Note),
+    Quit,
+}
+
+/// The twelve note names of one octave, repeated upward starting at C4, one
+/// per key in `sequence` in order.
+fn sequence_notes(sequence: &[char]) -> Vec<String> {
+    const CHROMATIC: [&str; 12] = ["C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B"];
+    sequence
+        .iter()
+        .enumerate()
+        .map(|(i, _)| format!("{}{}", CHROMATIC[i % 12], 4 + i / 12))
+        .collect()
+}
+
+/// The local piano: the keys drawn on screen, the colors used to draw them,
+/// and (optionally) the file this session's notes are being recorded to.
+/// Shared behind an `Arc<Mutex<...>>` so the game loop, the network-receive
+/// thread, and file playback can all play notes through it.
+pub struct PianoKeyboard {
+    sequence: Vec<char>,
+    notes: Vec<String>,
+    volume: f32,
+    note_duration: Duration,
+    mark_duration: Duration,
+    pub color: Color,
+    mark_color: Color,
+    idle_color: Color,
+    record_file: Option<File>,
+    last_recorded_at: Option<Instant>,
+    audio: mpsc::Sender<Vec<i16>>,
+}
+
+/// `rodio::OutputStream` isn't `Send`, so it can't live inside
+/// `PianoKeyboard` behind the shared `Arc<Mutex<...>>`, and must be opened
+/// on the thread that uses it. Instead a dedicated thread owns it and just
+/// relays PCM buffers handed to it over a channel, so `PianoKeyboard` itself
+/// only ever holds the `Sender` half.
+fn spawn_audio_thread() -> mpsc::Sender<Vec<i16>> {
+    let (samples_tx, samples_rx) = mpsc::channel::<Vec<i16>>();
+
+    thread::spawn(move || {
+        let (_stream, handle) = match OutputStream::try_default() {
+            Ok(output) => output,
+            Err(e) => {
+                eprintln!("no audio output device available, playing silently: {}", e);
+                return;
+            }
+        };
+
+        for samples in samples_rx {
+            let source = SamplesBuffer::new(1, SAMPLE_RATE, samples);
+            if let Err(e) = handle.play_raw(source.convert_samples()) {
+                eprintln!("failed to play note audio, skipping: {}", e);
+            }
+        }
+    });
+
+    samples_tx
+}
+
+impl PianoKeyboard {
+    pub fn new(
+        sequence: String,
+        volume: f32,
+        note_duration: Duration,
+        mark_duration: Duration,
+        color: Color,
+        mark_color: Color,
+        idle_color: Color,
+    ) -> PianoKeyboard {
+        let sequence: Vec<char> = sequence.chars().collect();
+        let notes = sequence_notes(&sequence);
+        let audio = spawn_audio_thread();
+
+        PianoKeyboard {
+            sequence,
+            notes,
+            volume,
+            note_duration,
+            mark_duration,
+            color,
+            mark_color,
+            idle_color,
+            record_file: None,
+            last_recorded_at: None,
+            audio,
+        }
+    }
+
+    pub fn set_note_color(&mut self, color: Color) {
+        self.color = color;
+    }
+
+    /// Opens `path` for appending and starts recording every note played
+    /// through this keyboard (local keypresses and remote/file notes alike)
+    /// as `<base_note> <duration_ms> <delay_ms>` lines.
+    pub fn set_record_file(&mut self, path: PathBuf) {
+        match OpenOptions::new().create(true).append(true).open(&path) {
+            Ok(file) => self.record_file = Some(file),
+            Err(e) => eprintln!("failed to open record file {}, not recording: {}", path.display(), e),
+        }
+    }
+
+    /// Draws every key in the sequence along the top row, idle-colored.
+    pub fn draw(&self, terminal: &Arc<Mutex<Terminal>>) {
+        let mut terminal = terminal.lock().unwrap();
+        for (i, key) in self.sequence.iter().enumerate() {
+            if let Err(e) = terminal.draw(i as u16, 0, &key.to_string(), self.idle_color) {
+                eprintln!("failed to draw keyboard, skipping: {}", e);
+            }
+        }
+    }
+
+    /// Turns a keystroke into a `Note`/`Quit`, or `None` for keys that
+    /// aren't mapped to either.
+    pub fn process_key(&mut self, key: KeyEvent) -> Option<GameEvent> {
+        match key.code {
+            KeyCode::Esc => Some(GameEvent::Quit),
+            KeyCode::Char(c) => {
+                let index = self.sequence.iter().position(|&k| k == c)?;
+                match Note::from(&self.notes[index], self.color, self.note_duration) {
+                    Ok(note) => Some(GameEvent::Note(note)),
+                    Err(e) => {
+                        eprintln!("skipping unplayable key '{}', {}", c, e);
+                        None
+                    }
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// Plays `note`'s audio, records it if a record file is set, and
+    /// highlights its key (if it has one on this keyboard) for
+    /// `note_duration`, then `mark_color` for `mark_duration`, then idle.
+    pub fn play_note(&mut self, note: Note, terminal: &Arc<Mutex<Terminal>>) {
+        self.record_note(&note);
+        self.play_audio(&note);
+
+        let index = match self.notes.iter().position(|n| *n == note.base_note) {
+            Some(index) => index,
+            None => return,
+        };
+        let key = self.sequence[index];
+
+        if let Err(e) = terminal.lock().unwrap().draw(index as u16, 0, &key.to_string(), note.color) {
+            eprintln!("failed to draw note, skipping: {}", e);
+        }
+
+        let note_duration = self.note_duration;
+        let mark_duration = self.mark_duration;
+        let mark_color = self.mark_color;
+        let idle_color = self.idle_color;
+        let terminal = terminal.clone();
+        thread::spawn(move || {
+            thread::sleep(note_duration);
+            if let Err(e) = terminal.lock().unwrap().draw(index as u16, 0, &key.to_string(), mark_color) {
+                eprintln!("failed to draw mark, skipping: {}", e);
+            }
+            thread::sleep(mark_duration);
+            if let Err(e) = terminal.lock().unwrap().draw(index as u16, 0, &key.to_string(), idle_color) {
+                eprintln!("failed to draw idle, skipping: {}", e);
+            }
+        });
+    }
+
+    fn record_note(&mut self, note: &Note) {
+        if self.record_file.is_none() {
+            return;
+        }
+        let now = Instant::now();
+        let delay = self.last_recorded_at.map(|at| now.duration_since(at)).unwrap_or_default();
+        self.last_recorded_at = Some(now);
+
+        if let Some(file) = self.record_file.as_mut() {
+            if let Err(e) = writeln!(file, "{} {} {}", note.base_note, note.duration.as_millis(), delay.as_millis()) {
+                eprintln!("failed to write note to record file, skipping: {}", e);
+            }
+        }
+    }
+
+    fn play_audio(&self, note: &Note) {
+        let samples: Vec<i16> = note
+            .samples()
+            .iter()
+            .map(|sample| (*sample as f32 * self.volume) as i16)
+            .collect();
+        if self.audio.send(samples).is_err() {
+            eprintln!("audio thread is gone, playing silently");
+        }
+    }
+}
+
+/// One note parsed out of a `.notes` file/record: the note name, how long
+/// it should ring, and how long to wait after the previous note before
+/// playing it.
+pub struct FileNote {
+    pub base_note: String,
+    pub duration: Duration,
+    pub delay: Duration,
+}
+
+/// Reads a recorded `.notes` file line by line (`<base_note> <duration_ms>
+/// <delay_ms>`), the format `PianoKeyboard::set_record_file` writes.
+pub struct NoteReader {
+    path: PathBuf,
+}
+
+impl NoteReader {
+    pub fn from(path: PathBuf) -> NoteReader {
+        NoteReader { path }
+    }
+
+    /// Parses every well-formed line, logging and skipping anything else
+    /// (including an unreadable file, which yields no notes rather than
+    /// panicking).
+    pub fn parse_notes(&self) -> Vec<FileNote> {
+        let contents = match std::fs::read_to_string(&self.path) {
+            Ok(contents) => contents,
+            Err(e) => {
+                eprintln!("failed to read notes file {}, skipping: {}", self.path.display(), e);
+                return Vec::new();
+            }
+        };
+
+        contents
+            .lines()
+            .filter_map(parse_note_line)
+            .collect()
+    }
+}
+
+fn parse_note_line(line: &str) -> Option<FileNote> {
+    let mut parts = line.split_whitespace();
+    let base_note = parts.next()?.to_string();
+    let duration_ms: u64 = parts.next()?.parse().ok()?;
+    let delay_ms: u64 = parts.next()?.parse().ok()?;
+    Some(FileNote {
+        base_note,
+        duration: Duration::from_millis(duration_ms),
+        delay: Duration::from_millis(delay_ms),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn note_from_rejects_non_ascii_without_panicking() {
+        let result = Note::from("\u{fffd}4", Color::Blue, Duration::from_millis(10));
+        assert!(result.is_err());
+    }
+}
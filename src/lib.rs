@@ -0,0 +1,5 @@
+pub mod arguments;
+pub mod error;
+pub mod game;
+pub mod network;
+pub mod terminal;
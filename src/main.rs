@@ -1,10 +1,7 @@
-use rustbox::{Color, RustBox};
-use std::default::Default;
 use std::sync::{Arc, Mutex};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use std::net::SocketAddr;
-use std::io::Result;
 use std::path::PathBuf;
 
 use piano_rs::arguments::Options;
@@ -20,116 +17,277 @@ use piano_rs::network::{
     Sender,
 };
 
+mod mpris;
+mod playlist;
+mod render;
+mod theme;
+
+use mpris::{PlaybackState, PlaybackStatus};
+use piano_rs::error;
+use piano_rs::terminal::Terminal;
+use playlist::Playlist;
+use theme::Theme;
+
+/// Handles a single incoming network packet. Returns `Err` only for
+/// recoverable faults (a bad packet, an unparseable address, a peer that
+/// won't register) so the caller can log and move on rather than taking
+/// down the whole multiplayer session.
 fn handle_network_receive_event(
     keyboard: &Arc<Mutex<PianoKeyboard>>,
-    rustbox: &Arc<Mutex<RustBox>>,
+    terminal: &Arc<Mutex<Terminal>>,
     event_sender: &Arc<Mutex<Sender>>,
     event_receiver: &Receiver,
-) {
-    let data = event_receiver.poll_event().unwrap();
+    theme: &Theme,
+) -> error::Result<()> {
+    let data = event_receiver.poll_event()?;
     match data.event {
         NetworkEvent::PlayerJoin(port) => {
-            let remote_receiver_addr: SocketAddr = format!("{}:{}", data.src.ip(), port)
-                .parse()
-                .unwrap();
+            let remote_receiver_addr: SocketAddr = format!("{}:{}", data.src.ip(), port).parse()?;
 
             event_sender.lock().unwrap()
                 .register_remote_socket(
-                    event_receiver.socket.local_addr().unwrap().port(), remote_receiver_addr
-                )
-                .unwrap();
+                    event_receiver.socket.local_addr()?.port(), remote_receiver_addr
+                )?;
         }
         NetworkEvent::Peers(port, mut peers) => {
-            peers[0] = format!("{}:{}", data.src.ip(), port).parse().unwrap();
+            peers[0] = format!("{}:{}", data.src.ip(), port).parse()?;
             event_sender.lock().unwrap().peer_addrs = peers;
         }
         NetworkEvent::ID(id) => {
-            keyboard.lock().unwrap().set_note_color(match id {
-                0 => Color::Blue,
-                1 => Color::Red,
-                2 => Color::Green,
-                3 => Color::Yellow,
-                4 => Color::Cyan,
-                5 => Color::Magenta,
-                _ => Color::Black,
-            });
+            keyboard.lock().unwrap().set_note_color(theme.color_for_player(id as usize));
         }
         NetworkEvent::Note(note) => {
-            keyboard.lock().unwrap().play_note(note, &rustbox);
+            keyboard.lock().unwrap().play_note(note, terminal);
         }
-       _ => { },
     }
+    Ok(())
 }
 
-fn game_loop(rustbox: &Arc<Mutex<RustBox>>, keyboard: &Arc<Mutex<PianoKeyboard>>, event_sender: &Arc<Mutex<Sender>>) {
+fn game_loop(terminal: &Arc<Mutex<Terminal>>, keyboard: &Arc<Mutex<PianoKeyboard>>, event_sender: &Arc<Mutex<Sender>>) {
     let duration = Duration::from_nanos(1000);
     loop {
-        let event = rustbox.lock().unwrap().peek_event(duration, false);
+        let event = terminal.lock().unwrap().poll_event(duration);
         match event {
-            Ok(rustbox::Event::KeyEvent(key)) => {
+            Ok(Some(key)) => {
                 match keyboard.lock().unwrap().process_key(key) {
                     Some(GameEvent::Note(note)) => {
-                        event_sender.lock().unwrap().tick(note).unwrap();
+                        if let Err(e) = event_sender.lock().unwrap().tick(note) {
+                            eprintln!("recoverable error sending note, skipping: {}", e);
+                        }
                     }
                     Some(GameEvent::Quit) => break,
                     None => { },
                 };
             }
-            Err(e) => panic!("{}", e),
-            _ => { },
+            Ok(None) => { },
+            Err(e) => eprintln!("recoverable error polling terminal, skipping: {}", e),
+        }
+    }
+}
+
+/// Why `play_from_file` stopped iterating notes, so `play_from_playlist`
+/// can tell a user-requested `Stop` (halt the whole playlist) apart from
+/// the track simply running out of notes or an MPRIS `Next` (both of
+/// which should move on to the next track).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PlaybackOutcome {
+    Finished,
+    Skipped,
+    Stopped,
+}
+
+/// Blocks the calling thread until `playback_state` leaves `Paused`. Does
+/// not update `position`; `play_from_file` adds the time spent in
+/// `sleep_interruptible` to it. Returns `Some` with the reason playback
+/// should stop, or `None` to keep going.
+fn await_unpaused(playback_state: &Arc<Mutex<PlaybackState>>) -> Option<PlaybackOutcome> {
+    loop {
+        let mut state = playback_state.lock().unwrap();
+        if state.status == PlaybackStatus::Stopped {
+            return Some(PlaybackOutcome::Stopped);
+        }
+        if state.skip_requested {
+            state.skip_requested = false;
+            return Some(PlaybackOutcome::Skipped);
+        }
+        match state.status {
+            PlaybackStatus::Playing => return None,
+            PlaybackStatus::Paused => {
+                drop(state);
+                thread::sleep(Duration::from_millis(50));
+            }
+            PlaybackStatus::Stopped => unreachable!(),
+        }
+    }
+}
+
+/// The same 50ms poll interval `await_unpaused` sleeps in while paused.
+const PAUSE_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Waits out the inter-note `delay`, the same way `await_unpaused` waits out
+/// a pause: in small chunks rather than one uninterruptible
+/// `thread::sleep`, so a `Stop`/`Next` from the MPRIS interface takes effect
+/// within a poll interval instead of waiting out the whole delay. Returns
+/// `Some` with the reason playback should stop, or `None` once the delay has
+/// fully elapsed.
+fn sleep_interruptible(delay: Duration, playback_state: &Arc<Mutex<PlaybackState>>) -> Option<PlaybackOutcome> {
+    let mut remaining = delay;
+    while remaining > Duration::from_secs(0) {
+        if let Some(outcome) = await_unpaused(playback_state) {
+            return Some(outcome);
         }
+        let chunk = remaining.min(PAUSE_POLL_INTERVAL);
+        thread::sleep(chunk);
+        remaining -= chunk;
     }
+    None
 }
 
-fn play_from_file(play_file: PathBuf, tempo: f32, keyboard: &Arc<Mutex<PianoKeyboard>>, event_sender: &Arc<Mutex<Sender>>) {
+fn play_from_file(
+    play_file: PathBuf,
+    tempo: f32,
+    keyboard: &Arc<Mutex<PianoKeyboard>>,
+    event_sender: &Arc<Mutex<Sender>>,
+    playback_state: &Arc<Mutex<PlaybackState>>,
+) -> PlaybackOutcome {
     let file_base_notes = NoteReader::from(play_file);
     for file_base_note in file_base_notes.parse_notes() {
-        let note = Note::from(
+        if let Some(outcome) = await_unpaused(playback_state) {
+            return outcome;
+        }
+
+        let note = match Note::from(
             file_base_note.base_note.as_str(),
             keyboard.lock().unwrap().color,
             file_base_note.duration,
-        ).unwrap();
+        ) {
+            Ok(note) => note,
+            Err(e) => {
+                eprintln!("skipping malformed note, {}", e);
+                continue;
+            }
+        };
         let normalized_delay = Duration::from_millis(
             (file_base_note.delay.as_millis() as f32 / tempo) as u64
         );
-        thread::sleep(normalized_delay);
-        event_sender.lock().unwrap().tick(note).unwrap();
+
+        let wait_start = Instant::now();
+        let outcome = sleep_interruptible(normalized_delay, playback_state);
+        playback_state.lock().unwrap().position += wait_start.elapsed();
+        if let Some(outcome) = outcome {
+            return outcome;
+        }
+
+        if let Err(e) = event_sender.lock().unwrap().tick(note) {
+            eprintln!("recoverable error sending note, skipping: {}", e);
+        }
     }
+
+    playback_state.lock().unwrap().status = PlaybackStatus::Stopped;
+    PlaybackOutcome::Finished
 }
 
-fn main() -> Result<()> {
+/// Plays every track of an XSPF playlist in order through `play_from_file`,
+/// falling back to `default_tempo` when a track doesn't specify its own,
+/// sleeping the track's `delay` before moving on to the next one. Repeats
+/// the whole playlist when `loop_playlist` is set.
+fn play_from_playlist(
+    playlist_file: PathBuf,
+    default_tempo: f32,
+    loop_playlist: bool,
+    keyboard: &Arc<Mutex<PianoKeyboard>>,
+    event_sender: &Arc<Mutex<Sender>>,
+    playback_state: &Arc<Mutex<PlaybackState>>,
+) {
+    let playlist = Playlist::from_file(playlist_file);
+
+    'playlist: loop {
+        for track in &playlist.tracks {
+            let tempo = track.tempo.unwrap_or(default_tempo);
+            let track_name = track
+                .location
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default();
+
+            {
+                let mut state = playback_state.lock().unwrap();
+                state.track_name = track_name;
+                state.tempo = tempo;
+                state.status = PlaybackStatus::Playing;
+                state.position = Duration::from_secs(0);
+            }
+
+            let outcome = play_from_file(track.location.clone(), tempo, keyboard, event_sender, playback_state);
+            if outcome == PlaybackOutcome::Stopped {
+                break 'playlist;
+            }
+
+            thread::sleep(track.delay);
+        }
+
+        if !loop_playlist {
+            break;
+        }
+    }
+}
+
+fn main() -> error::Result<()> {
     let arguments = Options::read();
 
+    if let Some(v) = arguments.render_file {
+        let notes_file = PathBuf::from(v);
+        let output_file = PathBuf::from(arguments.render_output.unwrap_or_else(|| "output.wav".to_string()));
+        if let Err(e) = render::render_to_file(
+            notes_file,
+            output_file,
+            arguments.volume,
+            arguments.max_samplerate,
+        ) {
+            eprintln!("failed to render audio: {}", e);
+        }
+        return Ok(());
+    }
+
     let receiver_address = arguments.receiver_address;
     let event_receiver = Receiver::new(receiver_address)?;
     let event_sender = Arc::new(Mutex::new(Sender::new(arguments.sender_address, arguments.host_address)?));
     let event_sender_clone = event_sender.clone();
 
-    let rustbox = Arc::new(Mutex::new(
-        RustBox::init(Default::default()).unwrap()
-    ));
+    let terminal = Arc::new(Mutex::new(Terminal::init()?));
+
+    let theme = arguments.theme
+        .map(PathBuf::from)
+        .map(Theme::load)
+        .unwrap_or_default();
 
     let keyboard = Arc::new(Mutex::new(PianoKeyboard::new(
         arguments.sequence,
         arguments.volume,
         Duration::from_millis(arguments.note_duration),
         Duration::from_millis(arguments.mark_duration),
-        Color::Blue,
+        theme.note_color,
+        theme.mark_color,
+        theme.idle_color,
     )));
 
-    keyboard.lock().unwrap().draw(&rustbox);
+    keyboard.lock().unwrap().draw(&terminal);
 
-    let clonebox = rustbox.clone();
+    let cloneterminal = terminal.clone();
     let cloneboard = keyboard.clone();
+    let clonetheme = theme.clone();
 
     thread::spawn(move || {
         loop {
-            handle_network_receive_event(
+            if let Err(e) = handle_network_receive_event(
                 &cloneboard,
-                &clonebox,
+                &cloneterminal,
                 &event_sender_clone,
-                &event_receiver
-            );
+                &event_receiver,
+                &clonetheme,
+            ) {
+                eprintln!("recoverable network error, skipping: {}", e);
+            }
         }
     });
 
@@ -142,17 +300,134 @@ fn main() -> Result<()> {
     if let Some(v) = arguments.play_file {
         let play_file = PathBuf::from(v);
         let tempo = arguments.play_file_tempo;
+        let loop_playlist = arguments.loop_playlist;
+        let is_playlist = play_file.extension().is_some_and(|ext| ext == "xspf");
+        let track_name = play_file
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        let playback_state = Arc::new(Mutex::new(PlaybackState::new(track_name, tempo)));
+        let mpris_state = playback_state.clone();
+        thread::spawn(move || {
+            if let Err(e) = mpris::serve(mpris_state) {
+                eprintln!("mpris service failed: {}", e);
+            }
+        });
+
         let fileboard = keyboard.clone();
         let file_notes_sender = event_sender.clone();
-        thread::spawn(move || play_from_file(
-            play_file,
-            tempo,
-            &fileboard,
-            &file_notes_sender
-        ));
+        thread::spawn(move || {
+            if is_playlist {
+                play_from_playlist(
+                    play_file,
+                    tempo,
+                    loop_playlist,
+                    &fileboard,
+                    &file_notes_sender,
+                    &playback_state,
+                );
+            } else {
+                let outcome = play_from_file(
+                    play_file,
+                    tempo,
+                    &fileboard,
+                    &file_notes_sender,
+                    &playback_state,
+                );
+                if outcome == PlaybackOutcome::Skipped {
+                    playback_state.lock().unwrap().status = PlaybackStatus::Stopped;
+                }
+            }
+        });
     }
 
-    game_loop(&rustbox, &keyboard, &event_sender);
+    game_loop(&terminal, &keyboard, &event_sender);
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write as _;
+
+    use piano_rs::terminal::Color;
+
+    fn keyboard() -> Arc<Mutex<PianoKeyboard>> {
+        Arc::new(Mutex::new(PianoKeyboard::new(
+            "q".to_string(),
+            0.0,
+            Duration::from_millis(10),
+            Duration::from_millis(10),
+            Color::Blue,
+            Color::White,
+            Color::Black,
+        )))
+    }
+
+    #[test]
+    fn play_from_file_skips_malformed_notes_and_finishes() {
+        let mut notes_path = std::env::temp_dir();
+        notes_path.push(format!("piano-rs-test-{}.notes", std::process::id()));
+        {
+            let mut file = std::fs::File::create(&notes_path).unwrap();
+            writeln!(file, "not-a-note 100 0").unwrap();
+            writeln!(file, "A4 10 0").unwrap();
+        }
+
+        let keyboard = keyboard();
+        let event_sender = Arc::new(Mutex::new(
+            Sender::new("127.0.0.1:0".parse().unwrap(), "127.0.0.1:19283".parse().unwrap()).unwrap(),
+        ));
+        let playback_state = Arc::new(Mutex::new(PlaybackState::new("test".to_string(), 1.0)));
+
+        let outcome = play_from_file(notes_path.clone(), 1.0, &keyboard, &event_sender, &playback_state);
+
+        std::fs::remove_file(&notes_path).ok();
+        assert_eq!(outcome, PlaybackOutcome::Finished);
+    }
+
+    #[test]
+    fn play_from_playlist_resets_position_for_each_track() {
+        let mut first_notes = std::env::temp_dir();
+        first_notes.push(format!("piano-rs-test-first-{}.notes", std::process::id()));
+        let mut second_notes = std::env::temp_dir();
+        second_notes.push(format!("piano-rs-test-second-{}.notes", std::process::id()));
+        let mut playlist_path = std::env::temp_dir();
+        playlist_path.push(format!("piano-rs-test-{}.xspf", std::process::id()));
+        {
+            // The first track accumulates position via its delay; the
+            // second has no notes, so it only finishes with a non-zero
+            // position if the reset before it was skipped.
+            let mut file = std::fs::File::create(&first_notes).unwrap();
+            writeln!(file, "A4 10 100").unwrap();
+            std::fs::File::create(&second_notes).unwrap();
+            std::fs::write(
+                &playlist_path,
+                format!(
+                    r#"<playlist><trackList>
+                        <track><location>{}</location></track>
+                        <track><location>{}</location></track>
+                    </trackList></playlist>"#,
+                    first_notes.display(),
+                    second_notes.display(),
+                ),
+            )
+            .unwrap();
+        }
+
+        let keyboard = keyboard();
+        let event_sender = Arc::new(Mutex::new(
+            Sender::new("127.0.0.1:0".parse().unwrap(), "127.0.0.1:19283".parse().unwrap()).unwrap(),
+        ));
+        let playback_state = Arc::new(Mutex::new(PlaybackState::new("test".to_string(), 1.0)));
+
+        play_from_playlist(playlist_path.clone(), 1.0, false, &keyboard, &event_sender, &playback_state);
+
+        std::fs::remove_file(&first_notes).ok();
+        std::fs::remove_file(&second_notes).ok();
+        std::fs::remove_file(&playlist_path).ok();
+        assert_eq!(playback_state.lock().unwrap().position, Duration::from_secs(0));
+    }
+}
@@ -0,0 +1,220 @@
+use std::convert::TryFrom;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use zbus::blocking::ConnectionBuilder;
+use zbus::dbus_interface;
+
+/// The transport-level status of a file-playback session, shared between
+/// `play_from_file` and the MPRIS D-Bus service so media keys and status-bar
+/// widgets can observe and drive it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlaybackStatus {
+    Playing,
+    Paused,
+    Stopped,
+}
+
+impl PlaybackStatus {
+    fn as_str(&self) -> &'static str {
+        match self {
+            PlaybackStatus::Playing => "Playing",
+            PlaybackStatus::Paused => "Paused",
+            PlaybackStatus::Stopped => "Stopped",
+        }
+    }
+}
+
+pub struct PlaybackState {
+    pub status: PlaybackStatus,
+    /// Set by the MPRIS `Next` method. Distinct from `status` so that a
+    /// media-key "next track" advances the current playlist track without
+    /// being indistinguishable from a `Stop`, which should halt playback
+    /// outright.
+    pub skip_requested: bool,
+    pub position: Duration,
+    pub track_name: String,
+    pub tempo: f32,
+}
+
+impl PlaybackState {
+    pub fn new(track_name: String, tempo: f32) -> Self {
+        PlaybackState {
+            status: PlaybackStatus::Playing,
+            skip_requested: false,
+            position: Duration::from_secs(0),
+            track_name,
+            tempo,
+        }
+    }
+}
+
+struct MediaPlayer2;
+
+#[dbus_interface(name = "org.mpris.MediaPlayer2")]
+impl MediaPlayer2 {
+    #[dbus_interface(property)]
+    fn identity(&self) -> String {
+        "piano-rs".to_string()
+    }
+
+    #[dbus_interface(property)]
+    fn can_quit(&self) -> bool {
+        false
+    }
+
+    #[dbus_interface(property)]
+    fn can_raise(&self) -> bool {
+        false
+    }
+
+    #[dbus_interface(property)]
+    fn has_track_list(&self) -> bool {
+        false
+    }
+}
+
+struct Player {
+    state: Arc<Mutex<PlaybackState>>,
+}
+
+#[dbus_interface(name = "org.mpris.MediaPlayer2.Player")]
+impl Player {
+    fn play(&mut self) {
+        self.state.lock().unwrap().status = PlaybackStatus::Playing;
+    }
+
+    fn pause(&mut self) {
+        self.state.lock().unwrap().status = PlaybackStatus::Paused;
+    }
+
+    fn play_pause(&mut self) {
+        let mut state = self.state.lock().unwrap();
+        state.status = match state.status {
+            PlaybackStatus::Playing => PlaybackStatus::Paused,
+            PlaybackStatus::Paused | PlaybackStatus::Stopped => PlaybackStatus::Playing,
+        };
+    }
+
+    fn stop(&mut self) {
+        self.state.lock().unwrap().status = PlaybackStatus::Stopped;
+    }
+
+    fn next(&mut self) {
+        self.state.lock().unwrap().skip_requested = true;
+    }
+
+    fn previous(&mut self) {
+        // No-op: piano-rs plays playlists forward-only, but the MPRIS
+        // `Player` interface must expose this method regardless of
+        // `CanGoPrevious`.
+    }
+
+    fn seek(&mut self, _offset: i64) {
+        // No-op: piano-rs note playback isn't seekable.
+    }
+
+    fn set_position(&mut self, _track_id: zbus::zvariant::ObjectPath, _position: i64) {
+        // No-op: piano-rs note playback isn't seekable.
+    }
+
+    fn open_uri(&mut self, _uri: String) {
+        // No-op: piano-rs only plays the `.notes`/`.xspf` file given on
+        // the command line.
+    }
+
+    #[dbus_interface(property)]
+    fn playback_status(&self) -> String {
+        self.state.lock().unwrap().status.as_str().to_string()
+    }
+
+    #[dbus_interface(property)]
+    fn rate(&self) -> f64 {
+        self.state.lock().unwrap().tempo as f64
+    }
+
+    #[dbus_interface(property)]
+    fn position(&self) -> i64 {
+        self.state.lock().unwrap().position.as_micros() as i64
+    }
+
+    #[dbus_interface(property)]
+    fn metadata(&self) -> std::collections::HashMap<String, zbus::zvariant::Value<'_>> {
+        let state = self.state.lock().unwrap();
+        let mut metadata = std::collections::HashMap::new();
+        metadata.insert(
+            "mpris:trackid".to_string(),
+            zbus::zvariant::Value::from(
+                zbus::zvariant::ObjectPath::try_from("/org/mpris/MediaPlayer2/CurrentTrack")
+                    .unwrap(),
+            ),
+        );
+        metadata.insert(
+            "xesam:title".to_string(),
+            zbus::zvariant::Value::from(state.track_name.clone()),
+        );
+        metadata
+    }
+
+    #[dbus_interface(property)]
+    fn can_play(&self) -> bool {
+        true
+    }
+
+    #[dbus_interface(property)]
+    fn can_pause(&self) -> bool {
+        true
+    }
+
+    #[dbus_interface(property)]
+    fn can_go_next(&self) -> bool {
+        true
+    }
+
+    #[dbus_interface(property)]
+    fn can_go_previous(&self) -> bool {
+        false
+    }
+
+    #[dbus_interface(property)]
+    fn can_seek(&self) -> bool {
+        false
+    }
+
+    #[dbus_interface(property)]
+    fn can_control(&self) -> bool {
+        true
+    }
+
+    #[dbus_interface(property)]
+    fn volume(&self) -> f64 {
+        1.0
+    }
+
+    #[dbus_interface(property)]
+    fn minimum_rate(&self) -> f64 {
+        0.25
+    }
+
+    #[dbus_interface(property)]
+    fn maximum_rate(&self) -> f64 {
+        4.0
+    }
+}
+
+/// Serves the `org.mpris.MediaPlayer2` / `org.mpris.MediaPlayer2.Player`
+/// interfaces on the session bus for as long as `state` is alive, letting
+/// system media keys drive file playback. Blocks the calling thread, so it
+/// should be run on its own `thread::spawn`, mirroring the network-receive
+/// thread in `main`.
+pub fn serve(state: Arc<Mutex<PlaybackState>>) -> zbus::Result<()> {
+    let _connection = ConnectionBuilder::session()?
+        .name("org.mpris.MediaPlayer2.piano_rs")?
+        .serve_at("/org/mpris/MediaPlayer2", MediaPlayer2)?
+        .serve_at("/org/mpris/MediaPlayer2", Player { state })?
+        .build()?;
+
+    loop {
+        std::thread::sleep(Duration::from_secs(60));
+    }
+}
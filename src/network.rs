@@ -0,0 +1,217 @@
+use std::net::{SocketAddr, UdpSocket};
+use std::time::Duration;
+
+use crate::error;
+use crate::game::Note;
+use crate::terminal::Color;
+
+/// A single event parsed off the wire, paired with the address it actually
+/// arrived from (as opposed to any address embedded in the packet itself,
+/// which may be stale behind NAT).
+pub struct Data {
+    pub event: NetworkEvent,
+    pub src: SocketAddr,
+}
+
+/// The hand-rolled, line-oriented UDP wire protocol `Sender`/`Receiver`
+/// speak: `JOIN <port>`, `PEERS <port> <csv-addrs>`, `ID <id>`,
+/// `NOTE <base_note> <duration_ms> <color_name>`.
+pub enum NetworkEvent {
+    /// A new player announcing itself to the host, carrying the port its
+    /// own `Receiver` listens on.
+    PlayerJoin(u16),
+    /// The host's reply to a `JOIN`, broadcasting the full peer list (slot
+    /// 0 reserved for the host itself) to every known peer.
+    Peers(u16, Vec<SocketAddr>),
+    /// The host assigning a newly-joined peer its palette slot.
+    ID(u8),
+    /// A note struck by a remote peer, to be played and drawn locally.
+    Note(Note),
+}
+
+fn parse_event(message: &str) -> error::Result<NetworkEvent> {
+    let malformed = || error::PianoError::InvalidPacket(message.to_string());
+
+    let mut parts = message.split_whitespace();
+    match parts.next() {
+        Some("JOIN") => {
+            let port: u16 = parts.next().and_then(|p| p.parse().ok()).ok_or_else(malformed)?;
+            Ok(NetworkEvent::PlayerJoin(port))
+        }
+        Some("PEERS") => {
+            let port: u16 = parts.next().and_then(|p| p.parse().ok()).ok_or_else(malformed)?;
+            let peers = parts
+                .next()
+                .unwrap_or("")
+                .split(',')
+                .filter(|addr| !addr.is_empty())
+                .map(|addr| addr.parse())
+                .collect::<Result<Vec<SocketAddr>, _>>()?;
+            // Slot 0 is the host's; a roster without it is malformed.
+            if peers.is_empty() {
+                return Err(malformed());
+            }
+            Ok(NetworkEvent::Peers(port, peers))
+        }
+        Some("ID") => {
+            let id: u8 = parts.next().and_then(|p| p.parse().ok()).ok_or_else(malformed)?;
+            Ok(NetworkEvent::ID(id))
+        }
+        Some("NOTE") => {
+            let base_note = parts.next().ok_or_else(malformed)?;
+            let duration_ms: u64 = parts.next().and_then(|p| p.parse().ok()).ok_or_else(malformed)?;
+            let color = parts.next().and_then(Color::parse).ok_or_else(malformed)?;
+            let note = Note::from(base_note, color, Duration::from_millis(duration_ms))
+                .map_err(|e| error::PianoError::InvalidPacket(e.to_string()))?;
+            Ok(NetworkEvent::Note(note))
+        }
+        _ => Err(malformed()),
+    }
+}
+
+/// The read half of the network session: one UDP socket, bound to the port
+/// other players send notes and roster updates to.
+pub struct Receiver {
+    pub socket: UdpSocket,
+}
+
+impl Receiver {
+    pub fn new(address: SocketAddr) -> error::Result<Receiver> {
+        Ok(Receiver {
+            socket: UdpSocket::bind(address)?,
+        })
+    }
+
+    /// Blocks until a packet arrives, then parses it. Returns `Err` for a
+    /// malformed packet rather than panicking, so the caller can log and
+    /// keep polling.
+    pub fn poll_event(&self) -> error::Result<Data> {
+        let mut buf = [0u8; 1024];
+        let (len, src) = self.socket.recv_from(&mut buf)?;
+        let message = String::from_utf8_lossy(&buf[..len]);
+        let event = parse_event(&message)?;
+        Ok(Data { event, src })
+    }
+}
+
+/// The write half of the network session: the socket notes/roster updates
+/// are sent from, and the list of peers currently known to be in the game
+/// (slot 0 is always the host).
+pub struct Sender {
+    socket: UdpSocket,
+    host_address: SocketAddr,
+    pub peer_addrs: Vec<SocketAddr>,
+}
+
+impl Sender {
+    pub fn new(sender_address: SocketAddr, host_address: SocketAddr) -> error::Result<Sender> {
+        Ok(Sender {
+            socket: UdpSocket::bind(sender_address)?,
+            host_address,
+            peer_addrs: vec![host_address],
+        })
+    }
+
+    /// Announces this player to the host, so it gets added to the shared
+    /// peer list and assigned a palette slot.
+    pub fn register_self(&mut self, receiver_port: u16) -> error::Result<()> {
+        self.socket
+            .send_to(format!("JOIN {}", receiver_port).as_bytes(), self.host_address)?;
+        Ok(())
+    }
+
+    /// Adds a newly-joined peer's receiver address to the shared roster,
+    /// broadcasts the updated roster to everyone (including the new peer),
+    /// and tells the new peer its assigned palette slot.
+    pub fn register_remote_socket(
+        &mut self,
+        local_port: u16,
+        remote_receiver_addr: SocketAddr,
+    ) -> error::Result<()> {
+        self.peer_addrs.push(remote_receiver_addr);
+
+        let peers_csv = self
+            .peer_addrs
+            .iter()
+            .map(|addr| addr.to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+        let peers_message = format!("PEERS {} {}", local_port, peers_csv);
+        for peer in &self.peer_addrs {
+            self.socket.send_to(peers_message.as_bytes(), peer)?;
+        }
+
+        let id = self.peer_addrs.len() - 1;
+        self.socket
+            .send_to(format!("ID {}", id).as_bytes(), remote_receiver_addr)?;
+        Ok(())
+    }
+
+    /// Broadcasts a struck note to every known peer.
+    pub fn tick(&mut self, note: Note) -> error::Result<()> {
+        let message = format!(
+            "NOTE {} {} {}",
+            note.base_note,
+            note.duration.as_millis(),
+            note.color.name()
+        );
+        for peer in &self.peer_addrs {
+            self.socket.send_to(message.as_bytes(), peer)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_event_reads_a_join() {
+        let event = parse_event("JOIN 4242").unwrap();
+        assert!(matches!(event, NetworkEvent::PlayerJoin(4242)));
+    }
+
+    #[test]
+    fn parse_event_reads_peers() {
+        let event = parse_event("PEERS 4242 127.0.0.1:1,127.0.0.1:2").unwrap();
+        match event {
+            NetworkEvent::Peers(port, peers) => {
+                assert_eq!(port, 4242);
+                assert_eq!(peers.len(), 2);
+            }
+            _ => panic!("expected Peers"),
+        }
+    }
+
+    #[test]
+    fn parse_event_reads_an_id() {
+        let event = parse_event("ID 3").unwrap();
+        assert!(matches!(event, NetworkEvent::ID(3)));
+    }
+
+    #[test]
+    fn parse_event_reads_a_note() {
+        let event = parse_event("NOTE A4 100 blue").unwrap();
+        assert!(matches!(event, NetworkEvent::Note(_)));
+    }
+
+    #[test]
+    fn parse_event_rejects_a_short_message() {
+        assert!(parse_event("NOTE A4").is_err());
+    }
+
+    #[test]
+    fn parse_event_rejects_an_unknown_command() {
+        assert!(parse_event("HELLO there").is_err());
+    }
+
+    #[test]
+    fn parse_event_rejects_a_non_ascii_note_without_panicking() {
+        // Mirrors what `String::from_utf8_lossy` produces for garbled bytes
+        // in `Receiver::poll_event`, rather than panicking on a byte-index
+        // that isn't a char boundary.
+        let message = format!("NOTE {}4 100 blue", char::REPLACEMENT_CHARACTER);
+        assert!(parse_event(&message).is_err());
+    }
+}
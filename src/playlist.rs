@@ -0,0 +1,145 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use quick_xml::events::Event;
+use quick_xml::Reader;
+
+/// One `<track>` entry parsed out of an XSPF playlist: the `.notes` file to
+/// play, an optional tempo override (falling back to the playlist-wide
+/// tempo when absent), and the delay to wait before starting the next
+/// track.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Track {
+    pub location: PathBuf,
+    pub tempo: Option<f32>,
+    pub delay: Duration,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Playlist {
+    pub tracks: Vec<Track>,
+}
+
+impl Playlist {
+    /// Parses the `<trackList>`/`<track>` elements of an XSPF file, reading
+    /// each `<location>` plus the `tempo`/`delay` extension fields. Tracks
+    /// missing a `<location>` are skipped. Falls back to an empty playlist,
+    /// logging instead of panicking, if `path` can't be read.
+    pub fn from_file(path: PathBuf) -> Playlist {
+        let xml = match std::fs::read_to_string(&path) {
+            Ok(xml) => xml,
+            Err(e) => {
+                eprintln!("failed to read playlist {}, skipping: {}", path.display(), e);
+                return Playlist::default();
+            }
+        };
+        let mut reader = Reader::from_str(&xml);
+        reader.trim_text(true);
+
+        let mut tracks = Vec::new();
+        let mut current: Option<PartialTrack> = None;
+        let mut current_tag = String::new();
+        let mut buf = Vec::new();
+
+        loop {
+            match reader.read_event(&mut buf) {
+                Ok(Event::Start(ref e)) => {
+                    let tag = String::from_utf8_lossy(e.name()).into_owned();
+                    if tag == "track" {
+                        current = Some(PartialTrack::default());
+                    }
+                    current_tag = tag;
+                }
+                Ok(Event::Text(e)) => {
+                    if let Some(track) = current.as_mut() {
+                        let text = e.unescape_and_decode(&reader).unwrap_or_default();
+                        match current_tag.as_str() {
+                            "location" => track.location = Some(PathBuf::from(text)),
+                            "tempo" => track.tempo = text.parse().ok(),
+                            "delay" => track.delay = text.parse().ok(),
+                            _ => {}
+                        }
+                    }
+                }
+                Ok(Event::End(ref e)) if String::from_utf8_lossy(e.name()) == "track" => {
+                    if let Some(track) = current.take() {
+                        if let Some(location) = track.location {
+                            tracks.push(Track {
+                                location,
+                                tempo: track.tempo,
+                                delay: Duration::from_millis(track.delay.unwrap_or(0)),
+                            });
+                        }
+                    }
+                }
+                Ok(Event::Eof) => break,
+                Err(_) => break,
+                _ => {}
+            }
+            buf.clear();
+        }
+
+        Playlist { tracks }
+    }
+}
+
+#[derive(Default)]
+struct PartialTrack {
+    location: Option<PathBuf>,
+    tempo: Option<f32>,
+    delay: Option<u64>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_playlist(name: &str, xml: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("piano-rs-playlist-test-{}.xspf", name));
+        std::fs::write(&path, xml).unwrap();
+        path
+    }
+
+    #[test]
+    fn from_file_parses_tracks_with_tempo_and_delay() {
+        let path = write_playlist(
+            "full",
+            r#"<playlist><trackList>
+                <track><location>one.notes</location><tempo>1.5</tempo><delay>250</delay></track>
+                <track><location>two.notes</location></track>
+            </trackList></playlist>"#,
+        );
+
+        let playlist = Playlist::from_file(path);
+
+        assert_eq!(playlist.tracks.len(), 2);
+        assert_eq!(playlist.tracks[0].location, PathBuf::from("one.notes"));
+        assert_eq!(playlist.tracks[0].tempo, Some(1.5));
+        assert_eq!(playlist.tracks[0].delay, Duration::from_millis(250));
+        assert_eq!(playlist.tracks[1].location, PathBuf::from("two.notes"));
+        assert_eq!(playlist.tracks[1].tempo, None);
+        assert_eq!(playlist.tracks[1].delay, Duration::from_millis(0));
+    }
+
+    #[test]
+    fn from_file_skips_tracks_missing_a_location() {
+        let path = write_playlist(
+            "missing-location",
+            r#"<playlist><trackList>
+                <track><tempo>2.0</tempo></track>
+                <track><location>kept.notes</location></track>
+            </trackList></playlist>"#,
+        );
+
+        let playlist = Playlist::from_file(path);
+
+        assert_eq!(playlist.tracks.len(), 1);
+        assert_eq!(playlist.tracks[0].location, PathBuf::from("kept.notes"));
+    }
+
+    #[test]
+    fn from_file_falls_back_to_empty_playlist_when_unreadable() {
+        let playlist = Playlist::from_file(PathBuf::from("/nonexistent/piano-rs-playlist.xspf"));
+        assert_eq!(playlist, Playlist::default());
+    }
+}
@@ -0,0 +1,183 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use hound::{SampleFormat, WavSpec, WavWriter};
+
+use piano_rs::game::{Note, NoteReader};
+use piano_rs::terminal::Color;
+
+const SAMPLE_RATE: u32 = 44_100;
+
+fn duration_to_samples(duration: Duration) -> usize {
+    (duration.as_secs_f64() * SAMPLE_RATE as f64) as usize
+}
+
+/// Mixes a `.notes` recording down into a single 44.1kHz PCM buffer by
+/// laying each note's own samples into an accumulator at its recorded
+/// delay, honoring `duration` and `volume`. Malformed note lines are
+/// skipped, as in `play_from_file`, but unlike there a skipped line's delay
+/// still advances the position of the notes after it.
+fn mix_notes(notes_file: PathBuf, volume: f32) -> Vec<i16> {
+    let mut accumulator: Vec<i32> = Vec::new();
+    let mut cursor = 0usize;
+
+    let file_base_notes = NoteReader::from(notes_file);
+    for file_base_note in file_base_notes.parse_notes() {
+        cursor += duration_to_samples(file_base_note.delay);
+
+        let note = match Note::from(
+            file_base_note.base_note.as_str(),
+            // Color is irrelevant to the rendered audio; any concrete value works.
+            Color::Blue,
+            file_base_note.duration,
+        ) {
+            Ok(note) => note,
+            Err(e) => {
+                eprintln!("skipping malformed note, {}", e);
+                continue;
+            }
+        };
+
+        let samples = note.samples();
+        if accumulator.len() < cursor + samples.len() {
+            accumulator.resize(cursor + samples.len(), 0);
+        }
+
+        for (i, sample) in samples.iter().enumerate() {
+            accumulator[cursor + i] += (*sample as f32 * volume) as i32;
+        }
+    }
+
+    accumulator
+        .into_iter()
+        .map(|sample| sample.clamp(i16::MIN as i32, i16::MAX as i32) as i16)
+        .collect()
+}
+
+/// Resamples `samples` down to `target_rate` via linear interpolation.
+/// Returns `samples` unchanged if it's already at or below `target_rate`.
+fn resample(samples: &[i16], target_rate: u32) -> Vec<i16> {
+    if target_rate >= SAMPLE_RATE || samples.is_empty() {
+        return samples.to_vec();
+    }
+
+    let ratio = SAMPLE_RATE as f64 / target_rate as f64;
+    let out_len = (samples.len() as f64 / ratio).ceil() as usize;
+    let mut out = Vec::with_capacity(out_len);
+
+    for i in 0..out_len {
+        let src_pos = i as f64 * ratio;
+        let idx = src_pos as usize;
+        let frac = src_pos - idx as f64;
+
+        let a = samples.get(idx).copied().unwrap_or(0) as f64;
+        let b = samples.get(idx + 1).copied().unwrap_or(a as i16) as f64;
+        out.push((a + (b - a) * frac) as i16);
+    }
+
+    out
+}
+
+fn write_wav(output_file: PathBuf, samples: &[i16], sample_rate: u32) -> std::io::Result<()> {
+    let spec = WavSpec {
+        channels: 1,
+        sample_rate,
+        bits_per_sample: 16,
+        sample_format: SampleFormat::Int,
+    };
+
+    let mut writer = WavWriter::create(output_file, spec)
+        .map_err(std::io::Error::other)?;
+    for sample in samples {
+        writer
+            .write_sample(*sample)
+            .map_err(std::io::Error::other)?;
+    }
+    writer
+        .finalize()
+        .map_err(std::io::Error::other)
+}
+
+#[cfg(feature = "vorbis")]
+fn write_vorbis(output_file: PathBuf, samples: &[i16], sample_rate: u32) -> std::io::Result<()> {
+    use std::fs::File;
+    use vorbis_rs::VorbisEncoderBuilder;
+
+    let file = File::create(output_file)?;
+    let channel_samples: Vec<f32> = samples.iter().map(|s| *s as f32 / i16::MAX as f32).collect();
+
+    let mut encoder = VorbisEncoderBuilder::new(
+        std::num::NonZeroU32::new(sample_rate).unwrap(),
+        std::num::NonZeroU8::new(1).unwrap(),
+        file,
+    )
+    .map_err(std::io::Error::other)?
+    .build()
+    .map_err(std::io::Error::other)?;
+
+    encoder
+        .encode_audio_block([&channel_samples])
+        .map_err(std::io::Error::other)?;
+    encoder
+        .finish()
+        .map_err(std::io::Error::other)?;
+    Ok(())
+}
+
+/// Renders a recorded `.notes` file to an audio file, resampling down to
+/// `max_samplerate` first when the mixed buffer exceeds it. Writes Vorbis
+/// (`.ogg`) when the `vorbis` feature is enabled and `output_file` has an
+/// `.ogg` extension, WAV otherwise.
+pub fn render_to_file(
+    notes_file: PathBuf,
+    output_file: PathBuf,
+    volume: f32,
+    max_samplerate: Option<u32>,
+) -> std::io::Result<()> {
+    let mixed = mix_notes(notes_file, volume);
+    let sample_rate = max_samplerate
+        .filter(|&rate| rate < SAMPLE_RATE)
+        .unwrap_or(SAMPLE_RATE);
+    let samples = resample(&mixed, sample_rate);
+
+    #[cfg(feature = "vorbis")]
+    {
+        if output_file.extension().and_then(|ext| ext.to_str()) == Some("ogg") {
+            return write_vorbis(output_file, &samples, sample_rate);
+        }
+    }
+
+    write_wav(output_file, &samples, sample_rate)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn duration_to_samples_converts_at_44_1khz() {
+        assert_eq!(duration_to_samples(Duration::from_secs(1)), SAMPLE_RATE as usize);
+        assert_eq!(duration_to_samples(Duration::from_millis(500)), SAMPLE_RATE as usize / 2);
+    }
+
+    #[test]
+    fn resample_is_a_noop_above_the_source_rate() {
+        let samples = vec![1, 2, 3, 4];
+        assert_eq!(resample(&samples, SAMPLE_RATE), samples);
+        assert_eq!(resample(&samples, SAMPLE_RATE * 2), samples);
+    }
+
+    #[test]
+    fn resample_is_a_noop_on_empty_input() {
+        let samples: Vec<i16> = Vec::new();
+        assert_eq!(resample(&samples, SAMPLE_RATE / 2), samples);
+    }
+
+    #[test]
+    fn resample_halves_the_buffer_at_half_the_rate() {
+        let samples: Vec<i16> = (0..100).collect();
+        let resampled = resample(&samples, SAMPLE_RATE / 2);
+        assert_eq!(resampled.len(), 50);
+        assert_eq!(resampled[0], samples[0]);
+    }
+}
@@ -0,0 +1,121 @@
+use std::io::{self, Stdout};
+use std::time::Duration;
+
+use crossterm::cursor;
+use crossterm::event::{self, Event as CrosstermEvent, KeyCode, KeyEvent as CrosstermKeyEvent};
+use crossterm::execute;
+use crossterm::style::{Color as CrosstermColor, Print, ResetColor, SetForegroundColor};
+use crossterm::terminal::{self, ClearType};
+
+/// The player palette, decoupled from any one terminal backend so the rest
+/// of the crate doesn't depend on crossterm's `Color` directly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Color {
+    Black,
+    Blue,
+    Red,
+    Green,
+    Yellow,
+    Cyan,
+    Magenta,
+    White,
+}
+
+impl From<Color> for CrosstermColor {
+    fn from(color: Color) -> Self {
+        match color {
+            Color::Black => CrosstermColor::Black,
+            Color::Blue => CrosstermColor::Blue,
+            Color::Red => CrosstermColor::Red,
+            Color::Green => CrosstermColor::Green,
+            Color::Yellow => CrosstermColor::Yellow,
+            Color::Cyan => CrosstermColor::Cyan,
+            Color::Magenta => CrosstermColor::Magenta,
+            Color::White => CrosstermColor::White,
+        }
+    }
+}
+
+impl Color {
+    /// Lowercase wire/config name for this color, the inverse of `parse`.
+    /// Used to round-trip a player's note color over the network; `theme`
+    /// reads its TOML color names through the same `parse`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Color::Black => "black",
+            Color::Blue => "blue",
+            Color::Red => "red",
+            Color::Green => "green",
+            Color::Yellow => "yellow",
+            Color::Cyan => "cyan",
+            Color::Magenta => "magenta",
+            Color::White => "white",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Color> {
+        match name.to_lowercase().as_str() {
+            "black" => Some(Color::Black),
+            "blue" => Some(Color::Blue),
+            "red" => Some(Color::Red),
+            "green" => Some(Color::Green),
+            "yellow" => Some(Color::Yellow),
+            "cyan" => Some(Color::Cyan),
+            "magenta" => Some(Color::Magenta),
+            "white" => Some(Color::White),
+            _ => None,
+        }
+    }
+}
+
+/// A key press, stripped down to what `PianoKeyboard::process_key` needs so
+/// callers don't have to match on crossterm's event enum directly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyEvent {
+    pub code: KeyCode,
+}
+
+/// Thin abstraction over the terminal backend, backed by `crossterm`
+/// instead of `rustbox`, so piano-rs runs on Windows and modern terminals.
+/// Lives in the library crate (rather than the binary) so `game`'s
+/// `PianoKeyboard::draw`/`play_note`/`process_key` can depend on it too.
+pub struct Terminal {
+    stdout: Stdout,
+}
+
+impl Terminal {
+    pub fn init() -> io::Result<Terminal> {
+        terminal::enable_raw_mode()?;
+        let mut stdout = io::stdout();
+        execute!(stdout, terminal::Clear(ClearType::All), cursor::Hide)?;
+        Ok(Terminal { stdout })
+    }
+
+    pub fn draw(&mut self, x: u16, y: u16, text: &str, color: Color) -> io::Result<()> {
+        execute!(
+            self.stdout,
+            cursor::MoveTo(x, y),
+            SetForegroundColor(color.into()),
+            Print(text),
+            ResetColor
+        )
+    }
+
+    /// Non-blocking poll with the same ~1µs timeout the rustbox backend
+    /// used, so the network-receive thread stays responsive.
+    pub fn poll_event(&self, timeout: Duration) -> io::Result<Option<KeyEvent>> {
+        if event::poll(timeout)? {
+            if let CrosstermEvent::Key(CrosstermKeyEvent { code, .. }) = event::read()? {
+                return Ok(Some(KeyEvent { code }));
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        let _ = execute!(self.stdout, cursor::Show);
+        let _ = terminal::disable_raw_mode();
+    }
+}
@@ -0,0 +1,160 @@
+use std::path::PathBuf;
+
+use serde::Deserialize;
+
+use piano_rs::terminal::Color;
+
+/// The set of colors `PianoKeyboard` draws with: the local player's note
+/// color, the color used to mark already-played keys, the idle key color,
+/// and the ordered palette assigned to remote players by ID.
+#[derive(Debug, Clone)]
+pub struct Theme {
+    pub note_color: Color,
+    pub mark_color: Color,
+    pub idle_color: Color,
+    pub player_palette: Vec<Color>,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme {
+            note_color: Color::Blue,
+            mark_color: Color::White,
+            idle_color: Color::Black,
+            player_palette: vec![
+                Color::Blue,
+                Color::Red,
+                Color::Green,
+                Color::Yellow,
+                Color::Cyan,
+                Color::Magenta,
+            ],
+        }
+    }
+}
+
+impl Theme {
+    /// Loads a theme from a TOML file, falling back to `Theme::default`
+    /// wholesale if the file can't be read or parsed, and per-field if the
+    /// file only defines some of them.
+    pub fn load(path: PathBuf) -> Theme {
+        let contents = match std::fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(e) => {
+                eprintln!("failed to read theme file, using defaults: {}", e);
+                return Theme::default();
+            }
+        };
+
+        match toml::from_str::<RawTheme>(&contents) {
+            Ok(raw) => raw.into_theme(),
+            Err(e) => {
+                eprintln!("failed to parse theme file, using defaults: {}", e);
+                Theme::default()
+            }
+        }
+    }
+
+    /// Returns the color assigned to remote player `id`, falling back to
+    /// `Color::Black` once `id` runs past the configured palette.
+    pub fn color_for_player(&self, id: usize) -> Color {
+        self.player_palette.get(id).copied().unwrap_or(Color::Black)
+    }
+}
+
+#[derive(Debug, Deserialize, Default)]
+struct RawTheme {
+    note_color: Option<String>,
+    mark_color: Option<String>,
+    idle_color: Option<String>,
+    player_palette: Option<Vec<String>>,
+}
+
+impl RawTheme {
+    fn into_theme(self) -> Theme {
+        let default = Theme::default();
+
+        let player_palette = self
+            .player_palette
+            .map(|names| {
+                names
+                    .iter()
+                    .enumerate()
+                    .map(|(i, name)| {
+                        Color::parse(name).unwrap_or_else(|| {
+                            let fallback = default
+                                .player_palette
+                                .get(i % default.player_palette.len())
+                                .copied()
+                                .unwrap_or(Color::Black);
+                            eprintln!(
+                                "invalid player_palette color '{}' at slot {}, using {:?} instead",
+                                name, i, fallback
+                            );
+                            fallback
+                        })
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .filter(|palette| !palette.is_empty())
+            .unwrap_or(default.player_palette);
+
+        Theme {
+            note_color: self.note_color.as_deref().and_then(Color::parse).unwrap_or(default.note_color),
+            mark_color: self.mark_color.as_deref().and_then(Color::parse).unwrap_or(default.mark_color),
+            idle_color: self.idle_color.as_deref().and_then(Color::parse).unwrap_or(default.idle_color),
+            player_palette,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn into_theme_falls_back_to_defaults_when_raw_is_empty() {
+        let theme = RawTheme::default().into_theme();
+        assert_eq!(theme.note_color, Theme::default().note_color);
+        assert_eq!(theme.player_palette, Theme::default().player_palette);
+    }
+
+    #[test]
+    fn into_theme_parses_valid_fields() {
+        let raw = RawTheme {
+            note_color: Some("red".to_string()),
+            mark_color: Some("green".to_string()),
+            idle_color: Some("white".to_string()),
+            player_palette: None,
+        };
+        let theme = raw.into_theme();
+        assert_eq!(theme.note_color, Color::Red);
+        assert_eq!(theme.mark_color, Color::Green);
+        assert_eq!(theme.idle_color, Color::White);
+    }
+
+    #[test]
+    fn into_theme_keeps_slot_alignment_for_an_invalid_palette_entry() {
+        let raw = RawTheme {
+            note_color: None,
+            mark_color: None,
+            idle_color: None,
+            player_palette: Some(vec!["red".to_string(), "bogus".to_string(), "green".to_string()]),
+        };
+        let theme = raw.into_theme();
+
+        // The invalid entry falls back to the default palette's own slot 1
+        // color rather than shifting "green" into slot 1.
+        assert_eq!(theme.player_palette.len(), 3);
+        assert_eq!(theme.player_palette[0], Color::Red);
+        assert_eq!(theme.player_palette[1], Theme::default().player_palette[1]);
+        assert_eq!(theme.player_palette[2], Color::Green);
+    }
+
+    #[test]
+    fn color_for_player_falls_back_past_the_palette() {
+        let theme = Theme::default();
+        let palette_len = theme.player_palette.len();
+        assert_eq!(theme.color_for_player(palette_len + 1), Color::Black);
+    }
+}